@@ -57,7 +57,10 @@ async fn main() {
             .collect::<Vec<_>>(); // Only show first 10 groups
         let sid = result.sid().unwrap_or_default();
         let enabled = result.enabled();
-        let llts = result.last_logon();
+        let llts = result
+            .last_logon()
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| "never".to_string());
         let dn = result.dn;
 
         println!(
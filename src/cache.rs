@@ -0,0 +1,191 @@
+//! Caching layer for repeated LDAP searches.
+//!
+//! Callers that expand groups or resolve SIDs to names during an audit tend to
+//! re-run the same search against the DC over and over. [`CachedLdap`] wraps a
+//! bound [`Ldap`] and memoizes search results in a [`concread::arcache::ARCache`],
+//! which (unlike a plain LRU) is scan-resistant: a one-off bulk scan during an
+//! audit won't evict the hot entries a repeated group expansion depends on.
+
+use std::time::{Duration, Instant};
+
+use concread::arcache::{ARCache, ARCacheBuilder};
+use ldap3::{Ldap, Scope, SearchEntry};
+
+use anyhow::Result;
+
+/// Cache key for a search: base DN, scope, filter, and the (sorted) list of
+/// requested attributes, so two logically-identical searches with attributes
+/// requested in a different order still hit the same cache entry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SearchCacheKey {
+    base: String,
+    scope: i32,
+    filter: String,
+    attrs: Vec<String>,
+}
+
+impl SearchCacheKey {
+    pub fn new<S: AsRef<str>>(base: &str, scope: Scope, filter: &str, attrs: &[S]) -> Self {
+        let mut attrs = attrs.iter().map(|a| a.as_ref().to_owned()).collect::<Vec<_>>();
+        attrs.sort_unstable();
+        SearchCacheKey {
+            base: base.to_owned(),
+            scope: scope as i32,
+            filter: filter.to_owned(),
+            attrs,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    entries: Vec<SearchEntry>,
+    inserted_at: Instant,
+}
+
+/// Hit/miss counters for tuning cache size and TTL.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// A bound [`Ldap`] that memoizes [`Ldap::search`] results for `ttl` before
+/// re-running the search against the DC.
+pub struct CachedLdap {
+    ldap: Ldap,
+    cache: ARCache<SearchCacheKey, CacheEntry>,
+    ttl: Duration,
+    stats: CacheStats,
+}
+
+impl CachedLdap {
+    /// Wrap `ldap`, caching up to `max_entries` distinct searches for `ttl` each.
+    pub fn new(ldap: Ldap, max_entries: usize, ttl: Duration) -> Self {
+        CachedLdap {
+            ldap,
+            cache: ARCacheBuilder::new()
+                .set_size(max_entries, 0)
+                .build()
+                .expect("cache size must be non-zero"),
+            ttl,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Current hit/miss counts since this `CachedLdap` was created.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Search, returning the cached result if one exists for this exact
+    /// `(base, scope, filter, attrs)` and hasn't exceeded its TTL; otherwise
+    /// run the search against the DC and cache the result.
+    pub async fn search<S: AsRef<str> + AsRef<[u8]> + Send + Sync>(
+        &mut self,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: &[S],
+    ) -> Result<Vec<SearchEntry>> {
+        let key = SearchCacheKey::new(base, scope, filter, attrs);
+
+        if let Some(entries) = lookup(&mut self.cache, &mut self.stats, self.ttl, &key) {
+            return Ok(entries);
+        }
+
+        let (raw_entries, _result) = self.ldap.search(base, scope, filter, attrs).await?.success()?;
+        let entries = raw_entries
+            .into_iter()
+            .map(SearchEntry::construct)
+            .collect::<Vec<_>>();
+
+        store(&mut self.cache, key, entries.clone());
+
+        Ok(entries)
+    }
+
+    /// Borrow the underlying bound connection for operations this wrapper doesn't cover.
+    pub fn ldap(&mut self) -> &mut Ldap {
+        &mut self.ldap
+    }
+}
+
+/// Return the cached result for `key` if it's present and younger than `ttl`,
+/// accounting the lookup as a hit or a miss. Pulled out of `CachedLdap::search`
+/// so the cache/TTL/stats bookkeeping can be exercised without a real `Ldap`.
+fn lookup(
+    cache: &mut ARCache<SearchCacheKey, CacheEntry>,
+    stats: &mut CacheStats,
+    ttl: Duration,
+    key: &SearchCacheKey,
+) -> Option<Vec<SearchEntry>> {
+    {
+        let mut read_txn = cache.read();
+        if let Some(entry) = read_txn.get(key) {
+            if entry.inserted_at.elapsed() < ttl {
+                stats.hits += 1;
+                return Some(entry.entries.clone());
+            }
+        }
+    }
+    stats.misses += 1;
+    None
+}
+
+/// Cache `entries` under `key`, stamped with the current time for TTL purposes.
+fn store(cache: &mut ARCache<SearchCacheKey, CacheEntry>, key: SearchCacheKey, entries: Vec<SearchEntry>) {
+    let mut write_txn = cache.write();
+    write_txn.insert(
+        key,
+        CacheEntry {
+            entries,
+            inserted_at: Instant::now(),
+        },
+    );
+    write_txn.commit();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache() -> ARCache<SearchCacheKey, CacheEntry> {
+        ARCacheBuilder::new().set_size(8, 0).build().unwrap()
+    }
+
+    #[test]
+    fn key_is_independent_of_attribute_order() {
+        let a = SearchCacheKey::new("dc=contoso,dc=com", Scope::Subtree, "(uid=a)", &["b", "a"]);
+        let b = SearchCacheKey::new("dc=contoso,dc=com", Scope::Subtree, "(uid=a)", &["a", "b"]);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn lookup_misses_then_hits_after_store() {
+        let mut cache = test_cache();
+        let mut stats = CacheStats::default();
+        let key = SearchCacheKey::new("dc=contoso,dc=com", Scope::Base, "(objectClass=*)", &["cn"]);
+
+        assert!(lookup(&mut cache, &mut stats, Duration::from_secs(60), &key).is_none());
+        assert_eq!((stats.hits, stats.misses), (0, 1));
+
+        store(&mut cache, key.clone(), Vec::new());
+
+        assert!(lookup(&mut cache, &mut stats, Duration::from_secs(60), &key).is_some());
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+
+    #[test]
+    fn expired_entries_count_as_misses() {
+        let mut cache = test_cache();
+        let mut stats = CacheStats::default();
+        let key = SearchCacheKey::new("dc=contoso,dc=com", Scope::Base, "(objectClass=*)", &["cn"]);
+
+        store(&mut cache, key.clone(), Vec::new());
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(lookup(&mut cache, &mut stats, Duration::from_millis(1), &key).is_none());
+        assert_eq!((stats.hits, stats.misses), (0, 1));
+    }
+}
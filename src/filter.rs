@@ -0,0 +1,126 @@
+//! RFC 4515-compliant LDAP filter construction.
+//!
+//! `Filter` builds a filter as an AST instead of a raw string, so assertion
+//! values get escaped once in [`Filter::to_filter_string`] rather than at
+//! every call site.
+
+use std::fmt::Write;
+
+/// A subset of the LDAP filter grammar (RFC 4515) sufficient for this crate's needs.
+pub enum Filter<'a> {
+    /// `(attribute=value)`
+    Equality(&'a str, &'a str),
+    /// `(attribute=initial*any*...*final)`
+    Substring(&'a str, Substring<'a>),
+    /// `(attribute=*)`
+    Present(&'a str),
+    /// `(&(filter)(filter)...)`
+    And(Vec<Filter<'a>>),
+    /// `(|(filter)(filter)...)`
+    Or(Vec<Filter<'a>>),
+}
+
+/// The three parts of a substring assertion, per RFC 4515's `substring` production.
+/// Any part left `None`/empty is omitted from the rendered filter.
+pub struct Substring<'a> {
+    pub initial: Option<&'a str>,
+    pub any: Vec<&'a str>,
+    pub final_: Option<&'a str>,
+}
+
+impl<'a> Filter<'a> {
+    /// Render this filter to its RFC 4515 string form, escaping every assertion
+    /// value byte-wise (`*`\u{2192}`\2a`, `(`\u{2192}`\28`, `)`\u{2192}`\29`, `\`\u{2192}`\5c`, NUL\u{2192}`\00`).
+    pub fn to_filter_string(&self) -> String {
+        let mut out = String::new();
+        self.write_filter_string(&mut out);
+        out
+    }
+
+    fn write_filter_string(&self, out: &mut String) {
+        match self {
+            Filter::Equality(attr, value) => {
+                let _ = write!(out, "({attr}={})", escape_filter_value(value));
+            }
+            Filter::Present(attr) => {
+                let _ = write!(out, "({attr}=*)");
+            }
+            Filter::Substring(attr, sub) => {
+                let _ = write!(out, "({attr}=");
+                if let Some(initial) = sub.initial {
+                    out.push_str(&escape_filter_value(initial));
+                }
+                out.push('*');
+                for any in &sub.any {
+                    out.push_str(&escape_filter_value(any));
+                    out.push('*');
+                }
+                if let Some(final_) = sub.final_ {
+                    out.push_str(&escape_filter_value(final_));
+                }
+                out.push(')');
+            }
+            Filter::And(filters) => {
+                out.push_str("(&");
+                filters.iter().for_each(|f| f.write_filter_string(out));
+                out.push(')');
+            }
+            Filter::Or(filters) => {
+                out.push_str("(|");
+                filters.iter().for_each(|f| f.write_filter_string(out));
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// Escape an assertion value per RFC 4515 section 3, byte-wise so the escaping
+/// is correct regardless of the value's encoding.
+pub fn escape_filter_value(value: &str) -> String {
+    let mut escaped = Vec::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'*' => escaped.extend_from_slice(b"\\2a"),
+            b'(' => escaped.extend_from_slice(b"\\28"),
+            b')' => escaped.extend_from_slice(b"\\29"),
+            b'\\' => escaped.extend_from_slice(b"\\5c"),
+            0x00 => escaped.extend_from_slice(b"\\00"),
+            _ => escaped.push(byte),
+        }
+    }
+    // Escaping only ever touches single-byte ASCII characters, so the untouched
+    // bytes of any multi-byte UTF-8 sequence stay intact and the result is valid UTF-8.
+    String::from_utf8(escaped).expect("escaping preserves UTF-8 validity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_bulk_filter;
+
+    #[test]
+    fn escapes_every_special_character() {
+        assert_eq!(escape_filter_value("*"), "\\2a");
+        assert_eq!(escape_filter_value("("), "\\28");
+        assert_eq!(escape_filter_value(")"), "\\29");
+        assert_eq!(escape_filter_value("\\"), "\\5c");
+        assert_eq!(escape_filter_value("\0"), "\\00");
+        assert_eq!(escape_filter_value("JSmith"), "JSmith");
+    }
+
+    #[test]
+    fn bulk_filter_neutralizes_wildcard_member() {
+        let filter = generate_bulk_filter(&["*"], "user", "samaccountname");
+        assert_eq!(filter, "(&(objectCategory=user)(|(samaccountname=\\2a)))");
+    }
+
+    #[test]
+    fn bulk_filter_neutralizes_filter_injection() {
+        let filter = generate_bulk_filter(&[")(objectClass=*"], "user", "samaccountname");
+        assert!(!filter.contains(")(objectClass=*)"));
+        assert_eq!(
+            filter,
+            "(&(objectCategory=user)(|(samaccountname=\\29\\28objectClass=\\2a)))"
+        );
+    }
+}
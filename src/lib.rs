@@ -1,11 +1,21 @@
 use anyhow::{bail, Error, Result};
-use byteorder::{LittleEndian, ReadBytesExt};
-use chrono::NaiveDateTime;
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use itertools::Itertools;
 use ldap3::{Ldap, LdapConnAsync, SearchEntry};
 use std::{borrow::Cow, fmt::Display, time::Duration};
 use trust_dns_resolver::TokioAsyncResolver;
 
+pub mod cache;
+pub mod filter;
+pub mod model;
+pub mod uac;
+pub mod write;
+
+use filter::Filter;
+pub use model::AdUser;
+pub use uac::UserAccountControl;
+
 pub async fn autoconnect_ldap(timeout: Option<Duration>) -> Result<Ldap, anyhow::Error> {
     let resolver = TokioAsyncResolver::tokio_from_system_conf()?;
     let lookup = resolver.srv_lookup("_ldap._tcp").await?;
@@ -59,18 +69,29 @@ pub fn generate_bulk_filter<
     category: T,
     attribute: U,
 ) -> String {
-    let mut filter = format!("(&(objectCategory={category})(|");
-    set.iter().for_each(|n| {
-        filter.push_str(format!("({attribute}={})", n).as_str());
-    });
-    filter.push_str("))");
-    filter
+    let category = category.to_string();
+    let attribute = attribute.to_string();
+    let members = set.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    Filter::And(vec![
+        Filter::Equality("objectCategory", &category),
+        Filter::Or(
+            members
+                .iter()
+                .map(|n| Filter::Equality(&attribute, n))
+                .collect(),
+        ),
+    ])
+    .to_filter_string()
 }
 
 pub trait AttributeHelper<'a> {
     fn int_attr(&self, attr_name: &str) -> Option<i64>;
     fn enabled(&self) -> bool;
-    fn last_logon(&self) -> NaiveDateTime;
+    fn uac(&self) -> UserAccountControl;
+    fn last_logon(&self) -> Option<DateTime<Utc>>;
+    fn pwd_last_set(&self) -> Option<DateTime<Utc>>;
+    fn account_expires(&self) -> Option<DateTime<Utc>>;
+    fn when_created(&self) -> Option<DateTime<Utc>>;
     fn str_attr(&'a mut self, name: &str) -> Option<String>;
     fn sid(&self) -> Result<String, anyhow::Error>;
     fn member_of(&'a mut self) -> Option<Vec<String>>;
@@ -89,7 +110,7 @@ impl<'a> AttributeHelper<'a> for SearchEntry {
     fn int_attr(&self, name: &str) -> Option<i64> {
         self.attrs
             .get(name)?
-            .get(0)
+            .first()
             .unwrap_or(&"0".to_string())
             .parse::<i64>()
             .ok()
@@ -100,88 +121,185 @@ impl<'a> AttributeHelper<'a> for SearchEntry {
     }
 
     fn enabled(&self) -> bool {
-        // Default to disabled (2)
-        self.int_attr("userAccountControl").unwrap_or(2) & 2 == 0
-        // bitwise and the uac number with 2; if it equals zero, the account is enabled
+        !self.uac().is_disabled()
     }
 
-    fn last_logon(&self) -> NaiveDateTime {
-        let last_logon = self.int_attr("lastLogonTimestamp").unwrap_or_default();
-        NaiveDateTime::from_timestamp(
-            (((last_logon as f64 / 10000000.0) as u64)
-                .saturating_sub(11644473600u64)
-                .saturating_sub(7 * 3600)) as _,
-            0,
+    fn uac(&self) -> UserAccountControl {
+        // Default to disabled (2)
+        UserAccountControl::from_bits_truncate(
+            self.int_attr("userAccountControl")
+                .unwrap_or(UserAccountControl::ACCOUNTDISABLE.bits()),
         )
     }
 
+    fn last_logon(&self) -> Option<DateTime<Utc>> {
+        filetime_to_utc(self.int_attr("lastLogonTimestamp")?)
+    }
+
+    fn pwd_last_set(&self) -> Option<DateTime<Utc>> {
+        filetime_to_utc(self.int_attr("pwdLastSet")?)
+    }
+
+    fn account_expires(&self) -> Option<DateTime<Utc>> {
+        filetime_to_utc(self.int_attr("accountExpires")?)
+    }
+
+    fn when_created(&self) -> Option<DateTime<Utc>> {
+        let raw = self.attrs.get("whenCreated")?.first()?;
+        generalized_time_to_utc(raw)
+    }
+
     fn sid(&self) -> Result<String, anyhow::Error> {
         let default = Vec::new();
         obj_sid_to_string(
             self.bin_attrs
                 .get("objectSid")
-                .map(|a| a.get(0).unwrap_or(&default))
+                .map(|a| a.first().unwrap_or(&default))
                 .unwrap_or(&default),
         )
     }
 }
 
-fn obj_sid_to_string(bytes: &[u8]) -> Result<String, anyhow::Error> {
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch (1970-01-01).
+const FILETIME_UNIX_EPOCH_DIFF_SECS: i64 = 11_644_473_600;
+/// Sentinel FILETIME AD uses for "never logged in" / "no such timestamp".
+const FILETIME_NEVER: i64 = 0;
+/// Sentinel FILETIME AD uses for "never expires".
+const FILETIME_NO_EXPIRY: i64 = 0x7FFF_FFFF_FFFF_FFFF;
+
+/// Convert a Windows FILETIME (100-nanosecond ticks since 1601-01-01 UTC, always
+/// UTC regardless of the DC's local timezone) to a UTC timestamp. Returns `None`
+/// for AD's `0` ("never") and `0x7FFFFFFFFFFFFFFF` ("never expires") sentinels
+/// rather than a bogus date (1601-01-01 for `0`, the year 30828 for the maximum).
+pub fn filetime_to_utc(filetime: i64) -> Option<DateTime<Utc>> {
+    if filetime == FILETIME_NEVER || filetime == FILETIME_NO_EXPIRY {
+        return None;
+    }
+    let unix_seconds = filetime / 10_000_000 - FILETIME_UNIX_EPOCH_DIFF_SECS;
+    Utc.timestamp_opt(unix_seconds, 0).single()
+}
+
+/// Parse an LDAP `GeneralizedTime` value (e.g. `whenCreated`'s `"20240115103000.0Z"`)
+/// to a UTC timestamp. Unlike `pwdLastSet`/`accountExpires`/`lastLogonTimestamp`,
+/// `whenCreated` is schema-typed as `GeneralizedTime`, not an Integer8 FILETIME,
+/// so it's a string to parse rather than ticks to convert.
+fn generalized_time_to_utc(raw: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(raw, "%Y%m%d%H%M%S%.fZ")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+pub(crate) fn obj_sid_to_string(bytes: &[u8]) -> Result<String, anyhow::Error> {
     let max_identifier_authority = 6;
     let max_sub_authorities = 15;
     let subauth_size = 4; // each subauth is 32-bits
 
     // The revision number is an unsigned 8-bit unsigned integer.
-    if let Some(revision) = bytes.get(0) {
-        // The number of sub-authority parts is specified as an 8-bit unsigned integer.
-        let subauth_count = bytes[*revision as usize] as usize;
+    let Some(revision) = bytes.first() else {
+        return Err(Error::msg("Couldn't get revision from SID"));
+    };
 
-        if subauth_count > max_sub_authorities {
-            bail!("SID exceeds the maximum number of sub authorities of {max_sub_authorities}")
-        }
+    // Revision (1) + subauth count (1) + identifier authority (6) must be present
+    // before we can even read the subauth count or the authority itself.
+    if bytes.len() < 8 {
+        bail!("SID array doesn't meet the minimum size requirement.")
+    }
 
-        let min_binary_length = *revision as usize + 1 + max_identifier_authority; // Revision (1) + subauth count (1) + identifier authority maximum (6)
-        let max_binary_length = min_binary_length + (subauth_count * subauth_size);
+    // The number of sub-authority parts is specified as an 8-bit unsigned integer at offset 1.
+    let subauth_count = bytes[1] as usize;
 
-        if bytes.len() < min_binary_length {
-            bail!("SID array doesn't meet the minimum size requirement.")
-        }
+    if subauth_count > max_sub_authorities {
+        bail!("SID exceeds the maximum number of sub authorities of {max_sub_authorities}")
+    }
 
-        if bytes.len() != max_binary_length {
-            bail!("According to byte {revision} of the SID its total length should be ({min_binary_length} + {subauth_size} * {subauth_count}) bytes, however its actual length is {} bytes.)", bytes.len());
-        }
-        // The powershell SID string doesn't appear to use the authority, so commented it out for now
-        // The authority is a 48-bit unsigned integer stored in big-endian format.
-        // let by = bytes.to_vec();
-        // let authority = by.as_slice().read_u48::<BigEndian>()?; // let authority = ByteBuffer.wrap(bytes).getLong() & mask_48_bit;
-
-        let mut sid_str = "S-".to_owned();
-        sid_str.push_str(revision.to_string().as_str());
-        sid_str.push('-');
-        sid_str.push_str(subauth_count.to_string().as_str());
-        // sid_str.push('-');
-        // sid_str.push_str(authority.to_string().as_str());
-
-        // The sub-authority consists of up to 255 32-bit unsigned integers in little-endian format. The number of integers is specified by numberOfSubAuthorityParts.
-        bytes[min_binary_length..bytes.len()]
-            .chunks_exact(subauth_size)
-            .into_iter()
-            .map(|mut a| a.read_u32::<LittleEndian>().unwrap_or_default())
-            .for_each(|sub_authority_part| {
-                sid_str.push('-');
-                sid_str.push_str(sub_authority_part.to_string().as_str());
-            });
-        Ok(sid_str)
-    } else {
-        Err(Error::msg("Couldn't get revision from SID"))
+    // Revision (1) + subauth count (1) + identifier authority maximum (6)
+    let min_binary_length = 1usize
+        .checked_add(1)
+        .and_then(|n| n.checked_add(max_identifier_authority))
+        .ok_or_else(|| Error::msg("SID length computation overflowed"))?;
+    let max_binary_length = subauth_count
+        .checked_mul(subauth_size)
+        .and_then(|n| n.checked_add(min_binary_length))
+        .ok_or_else(|| Error::msg("SID length computation overflowed"))?;
+
+    if bytes.len() < min_binary_length {
+        bail!("SID array doesn't meet the minimum size requirement.")
     }
+
+    if bytes.len() != max_binary_length {
+        bail!("According to byte 1 of the SID its total length should be ({min_binary_length} + {subauth_size} * {subauth_count}) bytes, however its actual length is {} bytes.)", bytes.len());
+    }
+
+    // The authority is a 48-bit unsigned integer stored in big-endian format at bytes 2..8.
+    let authority = (&bytes[2..8]).read_u48::<BigEndian>()?;
+
+    let mut sid_str = "S-".to_owned();
+    sid_str.push_str(revision.to_string().as_str());
+    sid_str.push('-');
+    sid_str.push_str(authority.to_string().as_str());
+
+    // The sub-authority consists of up to 15 (max_sub_authorities) 32-bit unsigned integers in little-endian format. The number of integers is specified by subauth_count.
+    bytes[min_binary_length..bytes.len()]
+        .chunks_exact(subauth_size)
+        .map(|mut a| a.read_u32::<LittleEndian>().unwrap_or_default())
+        .for_each(|sub_authority_part| {
+            sid_str.push('-');
+            sid_str.push_str(sub_authority_part.to_string().as_str());
+        });
+    Ok(sid_str)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn parses_generalized_time() {
+        let parsed = generalized_time_to_utc("20240115103000.0Z").unwrap();
+        assert_eq!(parsed.to_string(), "2024-01-15 10:30:00 UTC");
+    }
+
+    fn sid_bytes(authority: u64, sub_authorities: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![1u8, sub_authorities.len() as u8];
+        bytes.extend_from_slice(&authority.to_be_bytes()[2..]); // 48-bit big-endian authority
+        for sub_authority in sub_authorities {
+            bytes.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_known_sid_including_authority() {
+        let bytes = sid_bytes(5, &[21, 3623811015, 3361044348, 30300820, 1013]);
+        assert_eq!(
+            obj_sid_to_string(&bytes).unwrap(),
+            "S-1-5-21-3623811015-3361044348-30300820-1013"
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_sid_without_panicking() {
+        let bytes = [1u8, 5, 0, 0];
+        assert!(obj_sid_to_string(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_length_mismatched_with_subauth_count_without_panicking() {
+        // Claims 5 sub-authorities but only carries bytes for 1.
+        let mut bytes = sid_bytes(5, &[21]);
+        bytes[1] = 5;
+        assert!(obj_sid_to_string(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_too_many_sub_authorities_without_panicking() {
+        let bytes = sid_bytes(5, &[0; 16]);
+        assert!(obj_sid_to_string(&bytes).is_err());
+    }
 }
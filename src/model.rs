@@ -0,0 +1,153 @@
+//! Typed user model, built from a declarative attribute-name-to-field table so
+//! callers stop re-writing the `str_attr`/`int_attr`/`sid` boilerplate seen in
+//! `examples/example.rs`.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ldap3::SearchEntry;
+
+use crate::{filetime_to_utc, obj_sid_to_string, UserAccountControl};
+
+/// A user account, mapped from a search result's attributes.
+#[derive(Debug, Clone)]
+pub struct AdUser {
+    pub sam_account_name: String,
+    pub display_name: String,
+    pub employee_id: String,
+    pub title: String,
+    pub department: String,
+    pub sid: String,
+    pub member_of: Vec<String>,
+    pub uac: UserAccountControl,
+    pub last_logon: Option<DateTime<Utc>>,
+    pub pwd_last_set: Option<DateTime<Utc>>,
+    pub account_expires: Option<DateTime<Utc>>,
+    pub dn: String,
+}
+
+/// Case-insensitively remove `name` from a `str_attrs`-shaped map and take its
+/// first value, same as [`crate::AttributeHelper::str_attr`] but matching
+/// regardless of how the DC cased the attribute name.
+fn ci_take_one(attrs: &mut HashMap<String, Vec<String>>, name: &str) -> Option<String> {
+    let key = attrs.keys().find(|k| k.eq_ignore_ascii_case(name))?.clone();
+    attrs.remove(&key)?.into_iter().next()
+}
+
+/// Case-insensitive, multi-valued counterpart of [`ci_take_one`].
+fn ci_take_all(attrs: &mut HashMap<String, Vec<String>>, name: &str) -> Option<Vec<String>> {
+    let key = attrs.keys().find(|k| k.eq_ignore_ascii_case(name))?.clone();
+    attrs.remove(&key)
+}
+
+fn conv_str(entry: &mut SearchEntry, attr: &str) -> Result<String> {
+    Ok(ci_take_one(&mut entry.attrs, attr).unwrap_or_default())
+}
+
+fn conv_multi(entry: &mut SearchEntry, attr: &str) -> Result<Vec<String>> {
+    Ok(ci_take_all(&mut entry.attrs, attr).unwrap_or_default())
+}
+
+fn conv_filetime(entry: &mut SearchEntry, attr: &str) -> Result<Option<DateTime<Utc>>> {
+    Ok(ci_take_one(&mut entry.attrs, attr)
+        .and_then(|raw| raw.parse::<i64>().ok())
+        .and_then(filetime_to_utc))
+}
+
+fn conv_uac(entry: &mut SearchEntry, attr: &str) -> Result<UserAccountControl> {
+    let bits = ci_take_one(&mut entry.attrs, attr)
+        .and_then(|raw| raw.parse::<i64>().ok())
+        .unwrap_or(UserAccountControl::ACCOUNTDISABLE.bits());
+    Ok(UserAccountControl::from_bits_truncate(bits))
+}
+
+fn conv_sid(entry: &mut SearchEntry, attr: &str) -> Result<String> {
+    let key = entry
+        .bin_attrs
+        .keys()
+        .find(|k| k.eq_ignore_ascii_case(attr))
+        .cloned();
+    let bytes = key
+        .and_then(|key| entry.bin_attrs.remove(&key))
+        .and_then(|mut values| if values.is_empty() { None } else { Some(values.remove(0)) })
+        .unwrap_or_default();
+    obj_sid_to_string(&bytes)
+}
+
+/// Declares, in one place, each [`AdUser`] field's LDAP attribute name and the
+/// conversion used to read it, so [`AdUser::attrs`] and [`AdUser::from_entry`]
+/// can never drift out of sync. Attribute names are matched case-insensitively.
+macro_rules! ad_user_fields {
+    ($(($field:ident, $attr:literal, $conv:path)),+ $(,)?) => {
+        impl AdUser {
+            /// The attribute names to request so a search's entries can be mapped
+            /// with [`AdUser::from_entry`].
+            pub fn attrs() -> &'static [&'static str] {
+                &[$($attr),+]
+            }
+
+            /// Map a [`SearchEntry`]'s attributes onto an [`AdUser`] using the
+            /// field table given to `ad_user_fields!`.
+            pub fn from_entry(mut entry: SearchEntry) -> Result<AdUser> {
+                let dn = entry.dn.clone();
+                $(let $field = $conv(&mut entry, $attr)?;)+
+                Ok(AdUser { dn, $($field),+ })
+            }
+        }
+    };
+}
+
+ad_user_fields! {
+    (sam_account_name, "sAMAccountName", conv_str),
+    (display_name, "displayName", conv_str),
+    (employee_id, "employeeID", conv_str),
+    (title, "title", conv_str),
+    (department, "department", conv_str),
+    (sid, "objectSid", conv_sid),
+    (member_of, "memberOf", conv_multi),
+    (uac, "userAccountControl", conv_uac),
+    (last_logon, "lastLogonTimestamp", conv_filetime),
+    (pwd_last_set, "pwdLastSet", conv_filetime),
+    (account_expires, "accountExpires", conv_filetime),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sid_bytes(authority: u64, sub_authorities: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![1u8, sub_authorities.len() as u8];
+        bytes.extend_from_slice(&authority.to_be_bytes()[2..]);
+        for sub_authority in sub_authorities {
+            bytes.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn from_entry_matches_attribute_names_case_insensitively() {
+        let mut attrs = HashMap::new();
+        attrs.insert("samaccountname".to_string(), vec!["jsmith".to_string()]);
+        attrs.insert("DISPLAYNAME".to_string(), vec!["J Smith".to_string()]);
+        attrs.insert("MemberOf".to_string(), vec!["cn=admins,dc=contoso,dc=com".to_string()]);
+        attrs.insert("useraccountcontrol".to_string(), vec!["512".to_string()]);
+
+        let mut bin_attrs = HashMap::new();
+        bin_attrs.insert("objectsid".to_string(), vec![sid_bytes(5, &[21, 1, 2, 3])]);
+
+        let entry = SearchEntry {
+            dn: "cn=jsmith,dc=contoso,dc=com".to_string(),
+            attrs,
+            bin_attrs,
+        };
+
+        let user = AdUser::from_entry(entry).unwrap();
+
+        assert_eq!(user.sam_account_name, "jsmith");
+        assert_eq!(user.display_name, "J Smith");
+        assert_eq!(user.member_of, vec!["cn=admins,dc=contoso,dc=com".to_string()]);
+        assert_eq!(user.sid, "S-1-5-21-1-2-3");
+        assert!(!user.uac.is_disabled());
+    }
+}
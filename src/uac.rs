@@ -0,0 +1,79 @@
+//! Typed decoding of the AD `userAccountControl` bitmask.
+//!
+//! [`crate::AttributeHelper::enabled`] only ever tested the `ACCOUNTDISABLE` bit;
+//! this module decodes the rest of the word operators care about when auditing
+//! accounts.
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The subset of `userAccountControl` flags relevant to account auditing.
+    /// See <https://learn.microsoft.com/en-us/troubleshoot/windows-server/identity/useraccountcontrol-manipulate-account-properties> for the full bit layout.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct UserAccountControl: i64 {
+        const ACCOUNTDISABLE        = 0x2;
+        const LOCKOUT                = 0x10;
+        const PASSWD_NOTREQD         = 0x20;
+        const PASSWD_CANT_CHANGE     = 0x40;
+        const NORMAL_ACCOUNT         = 0x200;
+        const TRUSTED_FOR_DELEGATION = 0x80000;
+        const DONT_EXPIRE_PASSWORD   = 0x10000;
+        const SMARTCARD_REQUIRED     = 0x40000;
+        const PASSWORD_EXPIRED       = 0x800000;
+    }
+}
+
+impl UserAccountControl {
+    /// Whether `ACCOUNTDISABLE` is set.
+    pub fn is_disabled(&self) -> bool {
+        self.contains(UserAccountControl::ACCOUNTDISABLE)
+    }
+
+    /// Whether `LOCKOUT` is set.
+    pub fn is_locked(&self) -> bool {
+        self.contains(UserAccountControl::LOCKOUT)
+    }
+
+    /// Whether `DONT_EXPIRE_PASSWORD` is set.
+    pub fn password_never_expires(&self) -> bool {
+        self.contains(UserAccountControl::DONT_EXPIRE_PASSWORD)
+    }
+
+    /// Whether `PASSWD_NOTREQD` is set.
+    pub fn password_not_required(&self) -> bool {
+        self.contains(UserAccountControl::PASSWD_NOTREQD)
+    }
+
+    /// Whether `PASSWORD_EXPIRED` is set.
+    pub fn password_expired(&self) -> bool {
+        self.contains(UserAccountControl::PASSWORD_EXPIRED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_each_flag_from_a_combined_value() {
+        // NORMAL_ACCOUNT | DONT_EXPIRE_PASSWORD | LOCKOUT | PASSWORD_EXPIRED
+        let uac = UserAccountControl::from_bits_truncate(0x200 | 0x10000 | 0x10 | 0x800000);
+
+        assert!(!uac.is_disabled());
+        assert!(uac.is_locked());
+        assert!(uac.password_never_expires());
+        assert!(!uac.password_not_required());
+        assert!(uac.password_expired());
+    }
+
+    #[test]
+    fn accountdisable_alone_only_sets_is_disabled() {
+        let uac = UserAccountControl::from_bits_truncate(UserAccountControl::ACCOUNTDISABLE.bits());
+
+        assert!(uac.is_disabled());
+        assert!(!uac.is_locked());
+        assert!(!uac.password_never_expires());
+        assert!(!uac.password_not_required());
+        assert!(!uac.password_expired());
+    }
+}
@@ -0,0 +1,73 @@
+//! Mutating operations on top of a bound [`Ldap`].
+//!
+//! The rest of the crate only reads from AD; these are the inverse of the
+//! [`crate::AttributeHelper`] readers and let a caller manage accounts instead
+//! of just reporting on them.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use ldap3::{Ldap, Mod, Scope, SearchEntry};
+
+use crate::AttributeHelper;
+
+/// Reset `dn`'s password to `new_password`.
+///
+/// This is the Password Modify extended operation (OID 1.3.6.1.4.1.4203.1.11.1,
+/// RFC 3062) in spirit, but AD doesn't implement that exop for arbitrary
+/// password values: it expects the new password written directly to the
+/// `unicodePwd` attribute, quote-wrapped and UTF-16LE encoded, over LDAPS.
+pub async fn reset_password(ldap: &mut Ldap, dn: &str, new_password: &str) -> Result<()> {
+    let quoted = format!("\"{new_password}\"");
+    let encoded = quoted.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+
+    let mut value = HashSet::new();
+    value.insert(encoded);
+
+    ldap.modify(dn, vec![Mod::Replace(b"unicodePwd".to_vec(), value)])
+        .await?
+        .success()?;
+    Ok(())
+}
+
+/// Apply a batch of attribute changes (add/replace/delete), analogous to
+/// lldap's `LdapModifyRequest`.
+pub async fn set_attributes<S: AsRef<[u8]> + Eq + std::hash::Hash + Clone>(
+    ldap: &mut Ldap,
+    dn: &str,
+    changes: Vec<Mod<S>>,
+) -> Result<()> {
+    ldap.modify(dn, changes).await?.success()?;
+    Ok(())
+}
+
+const ACCOUNTDISABLE: i64 = 0x2;
+
+/// Enable or disable `dn`'s account by reading `userAccountControl`, flipping
+/// the `ACCOUNTDISABLE (0x2)` bit, and writing it back.
+pub async fn set_enabled(ldap: &mut Ldap, dn: &str, enabled: bool) -> Result<()> {
+    let (raw_entries, _) = ldap
+        .search(dn, Scope::Base, "(objectClass=*)", vec!["userAccountControl"])
+        .await?
+        .success()?;
+
+    let Some(raw_entry) = raw_entries.into_iter().next() else {
+        bail!("No such object: {dn}")
+    };
+    let entry = SearchEntry::construct(raw_entry);
+    let uac = entry.int_attr("userAccountControl").unwrap_or(ACCOUNTDISABLE);
+
+    let new_uac = if enabled {
+        uac & !ACCOUNTDISABLE
+    } else {
+        uac | ACCOUNTDISABLE
+    };
+
+    let mut value = HashSet::new();
+    value.insert(new_uac.to_string().into_bytes());
+
+    ldap.modify(dn, vec![Mod::Replace(b"userAccountControl".to_vec(), value)])
+        .await?
+        .success()?;
+    Ok(())
+}